@@ -0,0 +1,141 @@
+//! Conversion of validated IDX buffers into Arrow arrays, so that IDX files
+//! (the format MNIST ships in) can be fed straight into Arrow compute or
+//! written out as Parquet.
+//!
+//! This module is only compiled when the `arrow` feature is enabled.
+
+use std::{error::Error, fmt};
+
+use arrow::array::ArrayData;
+use arrow::buffer::Buffer;
+use arrow::datatypes::DataType;
+
+use crate::{ElementType, IdxArray, ValidationError};
+
+/// An error produced while converting an IDX buffer into an Arrow
+/// `ArrayData`.
+#[derive(Debug)]
+pub enum ArrowExportError {
+    /// The buffer did not pass [`crate::validate`].
+    Validation(ValidationError),
+    /// Arrow rejected the `ArrayData` we built from the buffer.
+    Arrow(arrow::error::ArrowError),
+}
+
+impl Error for ArrowExportError {}
+
+impl fmt::Display for ArrowExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Validation(err) => write!(f, "invalid IDX buffer: {err}"),
+            Self::Arrow(err) => write!(f, "arrow rejected the converted array: {err}"),
+        }
+    }
+}
+
+impl From<ValidationError> for ArrowExportError {
+    fn from(err: ValidationError) -> Self {
+        Self::Validation(err)
+    }
+}
+
+impl From<arrow::error::ArrowError> for ArrowExportError {
+    fn from(err: arrow::error::ArrowError) -> Self {
+        Self::Arrow(err)
+    }
+}
+
+fn data_type_for(element_type: ElementType) -> DataType {
+    match element_type {
+        ElementType::U8 => DataType::UInt8,
+        ElementType::I8 => DataType::Int8,
+        ElementType::I16 => DataType::Int16,
+        ElementType::I32 => DataType::Int32,
+        ElementType::F32 => DataType::Float32,
+        ElementType::F64 => DataType::Float64,
+    }
+}
+
+fn values_buffer(array: &IdxArray) -> Result<Buffer, ArrowExportError> {
+    // `to_vec` always performs the (possibly no-op) byte swap needed to turn
+    // the big-endian IDX payload into a native-endian Arrow buffer.
+    Ok(match array.element_type() {
+        ElementType::U8 => Buffer::from_vec(array.to_vec::<u8>().unwrap()),
+        ElementType::I8 => Buffer::from_vec(array.to_vec::<i8>().unwrap()),
+        ElementType::I16 => Buffer::from_vec(array.to_vec::<i16>().unwrap()),
+        ElementType::I32 => Buffer::from_vec(array.to_vec::<i32>().unwrap()),
+        ElementType::F32 => Buffer::from_vec(array.to_vec::<f32>().unwrap()),
+        ElementType::F64 => Buffer::from_vec(array.to_vec::<f64>().unwrap()),
+    })
+}
+
+/// A validated IDX buffer converted into a flat Arrow array, alongside the
+/// original N-D shape.
+///
+/// `data` is always one-dimensional; `shape` records how to reinterpret it,
+/// outermost dimension first, the same way [`IdxArray::shape`] does.
+pub struct ArrowArray {
+    pub shape: Vec<usize>,
+    pub data: ArrayData,
+}
+
+/// Validates `buffer` and converts it into an [`ArrowArray`].
+///
+/// The IDX type code is mapped to the matching Arrow primitive (`UInt8`,
+/// `Int8`, `Int16`, `Int32`, `Float32`, `Float64`) and the payload is copied
+/// into a native-endian Arrow `Buffer`. `ArrayDataBuilder::build` re-validates the
+/// declared length against the buffer's capacity, so a buffer that passes
+/// [`crate::validate`] is guaranteed to produce a well-formed `ArrayData`.
+pub fn to_arrow(buffer: &[u8]) -> Result<ArrowArray, ArrowExportError> {
+    let array = IdxArray::new(buffer)?;
+    let len: usize = array.shape().iter().product();
+
+    let data = ArrayData::builder(data_type_for(array.element_type()))
+        .len(len)
+        .add_buffer(values_buffer(&array)?)
+        .build()?;
+
+    Ok(ArrowArray {
+        shape: array.shape().to_vec(),
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int16Array, UInt8Array};
+    use arrow::datatypes::DataType;
+
+    use super::to_arrow;
+    use crate::write_idx;
+
+    /// Checks that a `u8` IDX buffer converts into a `UInt8Array` with the
+    /// same values and shape.
+    #[test]
+    fn test_to_arrow_uint8() {
+        let mut buffer = Vec::new();
+        write_idx(&mut buffer, &[3, 3], &[1u8, 0, 0, 0, 1, 0, 0, 0, 1]).unwrap();
+
+        let array = to_arrow(&buffer).unwrap();
+        assert_eq!(array.shape, vec![3, 3]);
+        assert_eq!(array.data.data_type(), &DataType::UInt8);
+
+        let values = UInt8Array::from(array.data);
+        assert_eq!(values.values(), &[1, 0, 0, 0, 1, 0, 0, 0, 1]);
+    }
+
+    /// Checks that a multi-byte element type is byte swapped into Arrow's
+    /// native-endian buffer rather than copied verbatim.
+    #[test]
+    fn test_to_arrow_int16() {
+        let mut buffer = Vec::new();
+        write_idx(&mut buffer, &[2], &[5i16, -5]).unwrap();
+
+        let array = to_arrow(&buffer).unwrap();
+        assert_eq!(array.shape, vec![2]);
+        assert_eq!(array.data.data_type(), &DataType::Int16);
+
+        let values = Int16Array::from(array.data);
+        assert_eq!(values.values(), &[5, -5]);
+    }
+}
@@ -1,7 +1,10 @@
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, io::Write};
+
+#[cfg(feature = "arrow")]
+pub mod arrow_export;
 
 #[derive(Debug, PartialEq)]
-enum ValidationError {
+pub enum ValidationError {
     Truncated,
     OverAllocated { declared: usize, actual: usize },
     BadPadding,
@@ -14,16 +17,110 @@ impl Error for ValidationError {}
 impl fmt::Display for ValidationError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Truncated => write!(f, "Buffer has been truncated"),
-            OverAllocated => write!(f, "Buffer has been overallocated"),
-            BadPadding => write!(f, "Unexpected bytes at beginning of buffer"),
-            UnknownTypeCode => write!(f, "Unrecognized type code"),
-            Overflow => write!(f, "Can't represent claimed bounds"),
+            Self::Truncated => write!(f, "Buffer has been truncated"),
+            Self::OverAllocated { declared, actual } => write!(
+                f,
+                "Buffer has been overallocated (declared {declared} bytes, found {actual})"
+            ),
+            Self::BadPadding => write!(f, "Unexpected bytes at beginning of buffer"),
+            Self::UnknownTypeCode { code } => write!(f, "Unrecognized type code {code:#04x}"),
+            Self::Overflow => write!(f, "Can't represent claimed bounds"),
+        }
+    }
+}
+
+/// The element type of an IDX array, decoded from the single type-code byte
+/// that follows the padding in the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+    U8,
+    I8,
+    I16,
+    I32,
+    F32,
+    F64,
+}
+
+impl ElementType {
+    fn from_code(code: u8) -> Result<Self, ValidationError> {
+        match code {
+            0x08 => Ok(Self::U8),
+            0x09 => Ok(Self::I8),
+            0x0B => Ok(Self::I16),
+            0x0C => Ok(Self::I32),
+            0x0D => Ok(Self::F32),
+            0x0E => Ok(Self::F64),
+            _ => Err(ValidationError::UnknownTypeCode { code }),
+        }
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::I16 => 2,
+            Self::I32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Self::U8 => 0x08,
+            Self::I8 => 0x09,
+            Self::I16 => 0x0B,
+            Self::I32 => 0x0C,
+            Self::F32 => 0x0D,
+            Self::F64 => 0x0E,
         }
     }
 }
 
-fn validate(buffer: &[u8]) -> Result<(), ValidationError> {
+/// Controls how [`validate_with_mode`] treats a buffer with trailing bytes
+/// beyond the declared header and payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValidationMode {
+    /// Reject any buffer longer than its declared length with
+    /// [`ValidationError::OverAllocated`]. This is what [`validate`] uses.
+    #[default]
+    Strict,
+    /// Accept a buffer longer than its declared length, ignoring the
+    /// trailing bytes. Useful when several IDX records are concatenated, or
+    /// when reading from a block device that pads to a fixed block size.
+    Lenient,
+}
+
+/// The outcome of a successful [`validate_with_mode`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationInfo {
+    /// The length of this record, i.e. the header plus its payload. A
+    /// streaming reader should advance its cursor by exactly this many
+    /// bytes to reach whatever follows.
+    pub len: usize,
+    /// The number of trailing bytes in the buffer beyond `len`. Always zero
+    /// in [`ValidationMode::Strict`], since those bytes would instead cause
+    /// an [`ValidationError::OverAllocated`].
+    pub trailing: usize,
+}
+
+/// Checks that `buffer` holds a well-formed IDX record, using
+/// [`ValidationMode::Strict`].
+///
+/// This is a thin wrapper around [`validate_with_mode`] for callers that
+/// don't need the [`ValidationInfo`] it reports. A buffer with trailing
+/// bytes is rejected with [`ValidationError::OverAllocated`].
+pub fn validate(buffer: &[u8]) -> Result<(), ValidationError> {
+    validate_with_mode(buffer, ValidationMode::Strict).map(|_| ())
+}
+
+/// Checks that `buffer` holds a well-formed IDX record and reports how many
+/// bytes it occupies.
+///
+/// See [`ValidationMode`] for how trailing bytes beyond the declared length
+/// are handled.
+pub fn validate_with_mode(
+    buffer: &[u8],
+    mode: ValidationMode,
+) -> Result<ValidationInfo, ValidationError> {
     if buffer.len() < 4 {
         return Err(ValidationError::Truncated);
     }
@@ -32,12 +129,382 @@ fn validate(buffer: &[u8]) -> Result<(), ValidationError> {
         return Err(ValidationError::BadPadding);
     }
 
-    return Ok(());
+    let element_type = ElementType::from_code(buffer[2])?;
+    let ndim = buffer[3] as usize;
+
+    let bounds_end = 4usize
+        .checked_add(
+            4usize
+                .checked_mul(ndim)
+                .ok_or(ValidationError::Overflow)?,
+        )
+        .ok_or(ValidationError::Overflow)?;
+    if buffer.len() < bounds_end {
+        return Err(ValidationError::Truncated);
+    }
+
+    let mut len: usize = 1;
+    for chunk in buffer[4..bounds_end].chunks_exact(4) {
+        let bound = u32::from_be_bytes(chunk.try_into().unwrap()) as usize;
+        len = len.checked_mul(bound).ok_or(ValidationError::Overflow)?;
+    }
+
+    let payload_size = len
+        .checked_mul(element_type.size())
+        .ok_or(ValidationError::Overflow)?;
+    let declared = bounds_end
+        .checked_add(payload_size)
+        .ok_or(ValidationError::Overflow)?;
+
+    let actual = buffer.len();
+    if actual < declared {
+        return Err(ValidationError::Truncated);
+    }
+    if actual > declared && mode == ValidationMode::Strict {
+        return Err(ValidationError::OverAllocated { declared, actual });
+    }
+
+    Ok(ValidationInfo {
+        len: declared,
+        trailing: actual - declared,
+    })
+}
+
+/// A scalar type that can appear as the element type of an IDX array.
+///
+/// This is implemented for exactly the types that have a corresponding IDX
+/// type code. It is sealed in practice because [`ElementType`] only covers
+/// those types, but the trait itself is left open so callers can still name
+/// it in bounds.
+pub trait IdxElement: Copy {
+    /// The IDX type code corresponding to `Self`.
+    const TYPE: ElementType;
+
+    /// Reads one big-endian-encoded value of `Self` from `bytes`, performing
+    /// a byte swap on little-endian hosts if needed.
+    ///
+    /// `bytes` must be exactly `size_of::<Self>()` long.
+    fn from_be_bytes(bytes: &[u8]) -> Self;
+
+    /// Appends the big-endian encoding of `self` to `out`, the inverse of
+    /// [`IdxElement::from_be_bytes`].
+    fn write_be_bytes(self, out: &mut Vec<u8>);
+}
+
+impl IdxElement for u8 {
+    const TYPE: ElementType = ElementType::U8;
+
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+
+    fn write_be_bytes(self, out: &mut Vec<u8>) {
+        out.push(self);
+    }
+}
+
+impl IdxElement for i8 {
+    const TYPE: ElementType = ElementType::I8;
+
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        bytes[0] as i8
+    }
+
+    fn write_be_bytes(self, out: &mut Vec<u8>) {
+        out.push(self as u8);
+    }
+}
+
+impl IdxElement for i16 {
+    const TYPE: ElementType = ElementType::I16;
+
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn write_be_bytes(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl IdxElement for i32 {
+    const TYPE: ElementType = ElementType::I32;
+
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn write_be_bytes(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl IdxElement for f32 {
+    const TYPE: ElementType = ElementType::F32;
+
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn write_be_bytes(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl IdxElement for f64 {
+    const TYPE: ElementType = ElementType::F64;
+
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn write_be_bytes(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+/// An error returned when a validated IDX buffer can't be reinterpreted as
+/// `&[T]` for some requested element type `T`.
+#[derive(Debug, PartialEq)]
+pub enum CastError {
+    /// `T::TYPE` does not match the array's actual element type.
+    TypeMismatch {
+        expected: ElementType,
+        found: ElementType,
+    },
+    /// The payload is not aligned correctly for `T`.
+    Misaligned,
+    /// `T` is wider than a single byte and the host is little-endian, so a
+    /// raw cast would silently reinterpret the big-endian IDX payload with
+    /// the wrong byte order. Use [`IdxArray::to_vec`] instead, which byte
+    /// swaps as it copies.
+    WouldRequireByteSwap,
+}
+
+impl Error for CastError {}
+
+impl fmt::Display for CastError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TypeMismatch { expected, found } => write!(
+                f,
+                "requested element type {expected:?} does not match array element type {found:?}"
+            ),
+            Self::Misaligned => write!(f, "payload is not correctly aligned for element type"),
+            Self::WouldRequireByteSwap => write!(
+                f,
+                "cannot zero-copy cast a multi-byte big-endian payload on a little-endian host"
+            ),
+        }
+    }
+}
+
+/// A zero-copy view of a validated IDX buffer.
+///
+/// Construct one with [`IdxArray::new`], which runs [`validate`] before
+/// slicing up the header so that every other method can assume the buffer is
+/// well-formed.
+pub struct IdxArray<'a> {
+    element_type: ElementType,
+    shape: Vec<usize>,
+    payload: &'a [u8],
+}
+
+impl<'a> IdxArray<'a> {
+    /// Validates `buffer` and builds a view over it.
+    pub fn new(buffer: &'a [u8]) -> Result<Self, ValidationError> {
+        validate(buffer)?;
+
+        // `buffer` is now known to be well-formed, so none of the parsing
+        // below can fail.
+        let element_type = ElementType::from_code(buffer[2]).unwrap();
+        let ndim = buffer[3] as usize;
+        let bounds_end = 4 + 4 * ndim;
+
+        let shape = buffer[4..bounds_end]
+            .chunks_exact(4)
+            .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()) as usize)
+            .collect();
+
+        Ok(Self {
+            element_type,
+            shape,
+            payload: &buffer[bounds_end..],
+        })
+    }
+
+    /// The element type stored in the buffer.
+    pub fn element_type(&self) -> ElementType {
+        self.element_type
+    }
+
+    /// The declared bounds of the array, outermost dimension first.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Returns the payload as a zero-copy `&[T]`, without performing a byte
+    /// swap.
+    ///
+    /// This only succeeds when `T::TYPE` matches the array's element type
+    /// and reinterpreting the big-endian payload bytes as native-endian `T`
+    /// is a no-op, i.e. `T` is a single byte wide (`u8`/`i8`) or the host
+    /// itself is big-endian. Everywhere else, use [`IdxArray::to_vec`].
+    pub fn as_slice<T: IdxElement>(&self) -> Result<&'a [T], CastError> {
+        if T::TYPE != self.element_type {
+            return Err(CastError::TypeMismatch {
+                expected: T::TYPE,
+                found: self.element_type,
+            });
+        }
+
+        let size = std::mem::size_of::<T>();
+        if size > 1 && cfg!(target_endian = "little") {
+            return Err(CastError::WouldRequireByteSwap);
+        }
+
+        if !(self.payload.as_ptr() as usize).is_multiple_of(std::mem::align_of::<T>()) {
+            return Err(CastError::Misaligned);
+        }
+
+        let len = self.payload.len() / size;
+        debug_assert!(len.checked_mul(size).unwrap() <= isize::MAX as usize);
+
+        // SAFETY: `T::TYPE` matches the decoded element type, so `payload`
+        // holds exactly `len` contiguous, correctly byte-ordered values of
+        // `T`; the pointer has just been checked for alignment, and the
+        // region can't exceed `isize::MAX` bytes because it is a subslice of
+        // the original `&[u8]`.
+        Ok(unsafe { std::slice::from_raw_parts(self.payload.as_ptr() as *const T, len) })
+    }
+
+    /// Copies the payload into a `Vec<T>`, byte swapping each element from
+    /// big-endian as needed.
+    pub fn to_vec<T: IdxElement>(&self) -> Result<Vec<T>, CastError> {
+        if T::TYPE != self.element_type {
+            return Err(CastError::TypeMismatch {
+                expected: T::TYPE,
+                found: self.element_type,
+            });
+        }
+
+        let size = std::mem::size_of::<T>();
+        Ok(self
+            .payload
+            .chunks_exact(size)
+            .map(T::from_be_bytes)
+            .collect())
+    }
+}
+
+/// An error returned by [`write_idx`] when `shape`/`data` can't be encoded
+/// as an IDX record.
+#[derive(Debug)]
+pub enum WriteError {
+    /// `shape` has more than 255 dimensions, which doesn't fit the
+    /// single-byte dimension count in the header.
+    TooManyDimensions,
+    /// One of `shape`'s bounds doesn't fit in the header's big-endian `u32`.
+    BoundTooLarge { bound: usize },
+    /// `element_size * product(shape)` (or the header plus that payload)
+    /// doesn't fit in `usize`/`isize`.
+    Overflow,
+    /// `data.len()` didn't match `product(shape)`.
+    LengthMismatch { expected: usize, actual: usize },
+    /// Writing to `w` failed.
+    Io(std::io::Error),
+}
+
+impl Error for WriteError {}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooManyDimensions => write!(f, "shape has more than 255 dimensions"),
+            Self::BoundTooLarge { bound } => {
+                write!(f, "bound {bound} does not fit in a 32 bit header field")
+            }
+            Self::Overflow => write!(f, "can't represent the encoded size of shape"),
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "data has {actual} elements, but shape implies {expected}"
+            ),
+            Self::Io(err) => write!(f, "failed to write IDX record: {err}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for WriteError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Serializes `data`, a flat buffer of `T` in row-major order with the given
+/// `shape`, as an IDX record.
+///
+/// The output always round-trips through [`validate`]: the two padding
+/// bytes, the type code for `T`, the dimension count, each bound as a
+/// big-endian `u32`, and finally the payload with every element written
+/// big-endian regardless of host endianness.
+pub fn write_idx<T: IdxElement, W: Write>(
+    mut w: W,
+    shape: &[usize],
+    data: &[T],
+) -> Result<(), WriteError> {
+    let ndim: u8 = shape
+        .len()
+        .try_into()
+        .map_err(|_| WriteError::TooManyDimensions)?;
+
+    let mut len: usize = 1;
+    for &bound in shape {
+        len = len.checked_mul(bound).ok_or(WriteError::Overflow)?;
+    }
+    if data.len() != len {
+        return Err(WriteError::LengthMismatch {
+            expected: len,
+            actual: data.len(),
+        });
+    }
+
+    let bounds_size = 4usize
+        .checked_mul(shape.len())
+        .ok_or(WriteError::Overflow)?;
+    let header_size = 4usize.checked_add(bounds_size).ok_or(WriteError::Overflow)?;
+    let payload_size = len
+        .checked_mul(std::mem::size_of::<T>())
+        .ok_or(WriteError::Overflow)?;
+    let total = header_size
+        .checked_add(payload_size)
+        .ok_or(WriteError::Overflow)?;
+    if total > isize::MAX as usize {
+        return Err(WriteError::Overflow);
+    }
+
+    w.write_all(&[0, 0, T::TYPE.code(), ndim])?;
+    for &bound in shape {
+        let bound: u32 = bound
+            .try_into()
+            .map_err(|_| WriteError::BoundTooLarge { bound })?;
+        w.write_all(&bound.to_be_bytes())?;
+    }
+
+    let mut payload = Vec::with_capacity(payload_size);
+    for &value in data {
+        value.write_be_bytes(&mut payload);
+    }
+    w.write_all(&payload)?;
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{validate, ValidationError};
+    use super::{
+        validate, validate_with_mode, write_idx, CastError, ElementType, IdxArray,
+        ValidationError, ValidationInfo, ValidationMode, WriteError,
+    };
 
     /// Checks that `idx_validate` correctly flags bad padding.
     #[test]
@@ -50,6 +517,7 @@ mod tests {
         ];
 
         for padding in 0x0001u16..=0xffffu16 {
+            data[0..2].copy_from_slice(&padding.to_be_bytes());
             assert_eq!(validate(&data), Err(ValidationError::BadPadding));
         }
     }
@@ -195,4 +663,164 @@ mod tests {
 
         assert_eq!(validate(&data), Ok(()));
     }
+
+    /// Checks that a `u8` array can be viewed with `as_slice` without a copy.
+    #[test]
+    fn test_as_slice_uint8() {
+        // A 3x3 identity matrix.
+        #[rustfmt::skip]
+        let data: [u8; 21] = [
+            0x00, 0x00, 0x08, 0x02,
+            0x00, 0x00, 0x00, 0x03,
+            0x00, 0x00, 0x00, 0x03,
+            0x01, 0x00, 0x00,
+            0x00, 0x01, 0x00,
+            0x00, 0x00, 0x01,
+        ];
+
+        let array = IdxArray::new(&data).unwrap();
+        assert_eq!(array.shape(), &[3, 3]);
+        assert_eq!(array.element_type(), ElementType::U8);
+        assert_eq!(array.as_slice::<u8>().unwrap(), &[1, 0, 0, 0, 1, 0, 0, 0, 1]);
+    }
+
+    /// Checks that `as_slice` rejects a mismatched element type.
+    #[test]
+    fn test_as_slice_type_mismatch() {
+        #[rustfmt::skip]
+        let data: [u8; 5] = [
+            0x00, 0x00, 0x08, 0x00,
+            0xfe,
+        ];
+
+        let array = IdxArray::new(&data).unwrap();
+        assert_eq!(
+            array.as_slice::<i16>(),
+            Err(CastError::TypeMismatch {
+                expected: ElementType::I16,
+                found: ElementType::U8,
+            })
+        );
+    }
+
+    /// Checks that `as_slice` refuses to hand out a multi-byte slice on a
+    /// little-endian host, since the bytes are big-endian in the buffer.
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_as_slice_rejects_byte_swap() {
+        #[rustfmt::skip]
+        let data: [u8; 6] = [
+            0x00, 0x00, 0x0B, 0x00,
+            0x01, 0x02,
+        ];
+
+        let array = IdxArray::new(&data).unwrap();
+        assert_eq!(array.as_slice::<i16>(), Err(CastError::WouldRequireByteSwap));
+    }
+
+    /// Checks that `to_vec` byte swaps multi-byte elements out of big-endian.
+    #[test]
+    fn test_to_vec_int16() {
+        #[rustfmt::skip]
+        let data: [u8; 12] = [
+            0x00, 0x00, 0x0B, 0x01,
+            0x00, 0x00, 0x00, 0x02,
+            0x00, 0x05, 0xFF, 0xFB,
+        ];
+
+        let array = IdxArray::new(&data).unwrap();
+        assert_eq!(array.to_vec::<i16>().unwrap(), vec![5, -5]);
+    }
+
+    /// Checks that strict mode still rejects a buffer with trailing bytes.
+    #[test]
+    fn test_validate_with_mode_strict_rejects_trailing() {
+        #[rustfmt::skip]
+        let data: [u8; 6] = [
+            0x00, 0x00, 0x08, 0x00,
+            0xfe, 0xff,
+        ];
+
+        assert_eq!(
+            validate_with_mode(&data, ValidationMode::Strict),
+            Err(ValidationError::OverAllocated {
+                declared: 5,
+                actual: 6,
+            })
+        );
+    }
+
+    /// Checks that lenient mode accepts trailing bytes and reports the slack
+    /// so a streaming reader can advance its cursor past this record.
+    #[test]
+    fn test_validate_with_mode_lenient_reports_trailing() {
+        #[rustfmt::skip]
+        let data: [u8; 6] = [
+            0x00, 0x00, 0x08, 0x00,
+            0xfe, 0xff,
+        ];
+
+        assert_eq!(
+            validate_with_mode(&data, ValidationMode::Lenient),
+            Ok(ValidationInfo { len: 5, trailing: 1 })
+        );
+    }
+
+    /// Checks that `write_idx` output round-trips through `validate` and
+    /// `IdxArray`.
+    #[test]
+    fn test_write_idx_round_trips() {
+        let mut out = Vec::new();
+        write_idx(&mut out, &[3, 3], &[1u8, 0, 0, 0, 1, 0, 0, 0, 1]).unwrap();
+
+        #[rustfmt::skip]
+        let expected: [u8; 21] = [
+            0x00, 0x00, 0x08, 0x02,
+            0x00, 0x00, 0x00, 0x03,
+            0x00, 0x00, 0x00, 0x03,
+            0x01, 0x00, 0x00,
+            0x00, 0x01, 0x00,
+            0x00, 0x00, 0x01,
+        ];
+        assert_eq!(out, expected);
+
+        validate(&out).unwrap();
+        let array = IdxArray::new(&out).unwrap();
+        assert_eq!(array.shape(), &[3, 3]);
+        assert_eq!(array.as_slice::<u8>().unwrap(), &[1, 0, 0, 0, 1, 0, 0, 0, 1]);
+    }
+
+    /// Checks that `write_idx` big-endian encodes multi-byte elements, and
+    /// that the result round-trips through `to_vec`.
+    #[test]
+    fn test_write_idx_int16_round_trips() {
+        let mut out = Vec::new();
+        write_idx(&mut out, &[2], &[5i16, -5]).unwrap();
+
+        #[rustfmt::skip]
+        let expected: [u8; 12] = [
+            0x00, 0x00, 0x0B, 0x01,
+            0x00, 0x00, 0x00, 0x02,
+            0x00, 0x05, 0xFF, 0xFB,
+        ];
+        assert_eq!(out, expected);
+
+        let array = IdxArray::new(&out).unwrap();
+        assert_eq!(array.to_vec::<i16>().unwrap(), vec![5, -5]);
+    }
+
+    /// Checks that `write_idx` rejects a length mismatch between `shape` and
+    /// `data` rather than writing a malformed record.
+    #[test]
+    fn test_write_idx_rejects_length_mismatch() {
+        let mut out = Vec::new();
+        let err = write_idx(&mut out, &[2, 2], &[1u8, 2, 3]).unwrap_err();
+        match err {
+            WriteError::LengthMismatch { expected, actual } => {
+                assert_eq!(expected, 4);
+                assert_eq!(actual, 3);
+            }
+            other => panic!("expected LengthMismatch, got {other:?}"),
+        }
+    }
 }